@@ -2,19 +2,130 @@ use std::fs;
 use std::io;
 
 extern crate clap;
+extern crate csv;
 extern crate fastanvil;
+extern crate flate2;
 extern crate rayon;
 extern crate regex;
+extern crate serde_json;
 
 use clap::Parser;
 use fastanvil::Chunk;
 use rayon::prelude::*;
 
+mod scan;
+
+#[derive(serde::Serialize)]
 pub struct BlockResults {
+    pub dimension: Dimension,
     pub chunk: (i32, i32, i32),
     pub blocks: Vec<((i32, i32, i32), String)>,
 }
 
+/// One of a world save's dimensions, each with its own `region` subfolder.
+#[derive(clap::ValueEnum, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    /// Region subfolder for this dimension, relative to the world save root.
+    fn region_subdir(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "region",
+            Dimension::Nether => "DIM-1/region",
+            Dimension::End => "DIM1/region",
+        }
+    }
+}
+
+/// `--dimension all` vs. a single dimension to restrict the search to.
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DimensionFilter {
+    #[default]
+    All,
+    Overworld,
+    Nether,
+    End,
+}
+
+impl DimensionFilter {
+    fn matches(self, dimension: Dimension) -> bool {
+        match self {
+            DimensionFilter::All => true,
+            DimensionFilter::Overworld => dimension == Dimension::Overworld,
+            DimensionFilter::Nether => dimension == Dimension::Nether,
+            DimensionFilter::End => dimension == Dimension::End,
+        }
+    }
+
+    /// Dimension to assume for a bare region directory (no dimension
+    /// subfolders found), honoring an explicit `--dimension` choice.
+    fn fallback_dimension(self) -> Dimension {
+        match self {
+            DimensionFilter::All | DimensionFilter::Overworld => Dimension::Overworld,
+            DimensionFilter::Nether => Dimension::Nether,
+            DimensionFilter::End => Dimension::End,
+        }
+    }
+}
+
+/// Discover which dimension subfolders exist under `root`, restricted to
+/// `dimension_filter`. If none of the matching dimension subfolders exist,
+/// `root` itself is treated as a single region directory (its contents are
+/// not checked) for backwards compatibility with a bare
+/// `--path <region dir>` invocation, tagged with `dimension_filter`'s
+/// dimension (or overworld, if the filter is `all`).
+fn discover_dimensions(
+    root: &std::path::Path,
+    dimension_filter: DimensionFilter,
+) -> Vec<(Dimension, std::path::PathBuf)> {
+    let dimensions = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+    let found: Vec<_> = dimensions
+        .into_iter()
+        .filter(|d| dimension_filter.matches(*d))
+        .map(|d| (d, root.join(d.region_subdir())))
+        .filter(|(_, dir)| dir.is_dir())
+        .collect();
+
+    if !found.is_empty() {
+        return found;
+    }
+    vec![(dimension_filter.fallback_dimension(), root.to_path_buf())]
+}
+
+/// Matches block names either against a comma-separated list of substrings
+/// or, under `--regex`, a single compiled regular expression.
+pub enum BlockMatcher {
+    Substrings(Vec<String>),
+    Regex(regex::Regex),
+}
+
+impl BlockMatcher {
+    pub fn parse(pattern: &str, use_regex: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        if use_regex {
+            Ok(BlockMatcher::Regex(regex::Regex::new(pattern)?))
+        } else {
+            Ok(BlockMatcher::Substrings(
+                pattern.split(',').map(|s| s.trim().to_string()).collect(),
+            ))
+        }
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            BlockMatcher::Substrings(substrings) => {
+                substrings.iter().any(|s| name.contains(s.as_str()))
+            }
+            BlockMatcher::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
 pub fn region_coordinates(filename: &str) -> Result<(i32, i32), Box<dyn std::error::Error>> {
     let captures = regex::Regex::new(r"r\.(-?\d+).(-?\d+)\.mca")?
         .captures(filename)
@@ -25,16 +136,27 @@ pub fn region_coordinates(filename: &str) -> Result<(i32, i32), Box<dyn std::err
     ))
 }
 
+/// Filters threaded through [`find_blocks`], bundled into one struct to keep
+/// the function's argument count clippy-clean.
+#[derive(Default)]
+pub struct FindBlocksOptions<'a> {
+    pub exclude: Option<&'a regex::Regex>,
+    pub chunk_distance_filter: Option<((i32, i32), i32)>,
+    pub y_bounds: Option<(i32, i32)>,
+    pub min_count: Option<usize>,
+}
+
 pub fn find_blocks<S: io::Read + io::Seek>(
     filename: &str,
     stream: S,
-    block_name: &str,
-    chunk_distance_filter: Option<((i32, i32), i32)>,
+    dimension: Dimension,
+    matcher: &BlockMatcher,
+    options: &FindBlocksOptions,
 ) -> Result<Vec<BlockResults>, Box<dyn std::error::Error>> {
     let (region_x, region_z) = region_coordinates(filename)?;
-    println!("{:>6} {:>6} | {}", region_x, region_z, filename);
+    eprintln!("{:>6} {:>6} | {}", region_x, region_z, filename);
 
-    if let Some(((from_x, from_z), maxdist)) = chunk_distance_filter {
+    if let Some(((from_x, from_z), maxdist)) = options.chunk_distance_filter {
         if (region_x - from_x).pow(2) + (region_z - from_z).pow(2) > maxdist.pow(2) {
             return Ok(vec![]);
         }
@@ -53,7 +175,7 @@ pub fn find_blocks<S: io::Read + io::Seek>(
         let chunk_z = region_z + (chunk.z as i32) * 16;
         let chunk_y: i32 = complete_chunk.y_range().start as i32;
 
-        if let Some(((from_x, from_z), maxdist)) = chunk_distance_filter {
+        if let Some(((from_x, from_z), maxdist)) = options.chunk_distance_filter {
             if (chunk_x - from_x).pow(2) + (chunk_z - from_z).pow(2) > maxdist.pow(2) {
                 continue;
             }
@@ -62,21 +184,39 @@ pub fn find_blocks<S: io::Read + io::Seek>(
         let found_blocks = complete_chunk
             .iter_blocks()
             .enumerate()
-            .filter(|(_, block)| block.name().contains(block_name))
+            .filter(|(_, block)| matcher.is_match(block.name()))
+            .filter(|(_, block)| {
+                !options
+                    .exclude
+                    .map(|re| re.is_match(block.name()))
+                    .unwrap_or(false)
+            })
             .map(|(block_index, block)| {
                 let x = chunk_x + (block_index as i32) % 16;
                 let z = chunk_z + ((block_index as i32) / 16) % 16;
                 let y = chunk_y + (block_index as i32) / (16 * 16);
                 ((x, y, z), block.name().to_string())
             })
+            .filter(|((_, y, _), _)| {
+                let (min_y, max_y) = options.y_bounds.unwrap_or((i32::MIN, i32::MAX));
+                *y >= min_y && *y <= max_y
+            })
             .collect::<Vec<_>>();
 
-        if !found_blocks.is_empty() {
-            results.push(BlockResults {
-                chunk: (chunk_x, chunk_y, chunk_z),
-                blocks: found_blocks,
-            });
+        if found_blocks.is_empty() {
+            continue;
+        }
+        if let Some(min_count) = options.min_count {
+            if found_blocks.len() < min_count {
+                continue;
+            }
         }
+
+        results.push(BlockResults {
+            dimension,
+            chunk: (chunk_x, chunk_y, chunk_z),
+            blocks: found_blocks,
+        });
     }
 
     Ok(results)
@@ -89,14 +229,31 @@ pub struct FileConfig {
     pub home: Option<(i32, i32)>,
     pub show_all: Option<bool>,
     pub max_distance: Option<i32>,
+    pub min_y: Option<i32>,
+    pub max_y: Option<i32>,
+    pub min_count: Option<usize>,
+    pub regex: Option<bool>,
+    pub exclude: Option<String>,
+    pub format: Option<OutputFormat>,
+    pub dimension: Option<DimensionFilter>,
+}
+
+/// How to render the final `BlockResults` list.
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
 }
 
 #[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Substring of the block name to search for
+    /// Comma-separated substrings of the block name to search for, or a regex under --regex
     block: Option<String>,
-    /// Region file directory (e.g. %APPDATA%/.minecraft/saves/world/region)
+    /// World save folder (e.g. %APPDATA%/.minecraft/saves/world), or a bare region directory
     #[arg(short, long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
     path: Option<std::path::PathBuf>,
     /// Whether to show all blocks rather than only chunks containing them
@@ -105,6 +262,36 @@ struct Cli {
     /// Whether to show all blocks rather than only chunks containing them
     #[arg(short, long)]
     max_distance: Option<i32>,
+    /// Minimum y-level (inclusive) a matched block must be at
+    #[arg(long)]
+    min_y: Option<i32>,
+    /// Maximum y-level (inclusive) a matched block must be at
+    #[arg(long)]
+    max_y: Option<i32>,
+    /// Suppress chunk results with fewer than this many matched blocks
+    #[arg(long)]
+    min_count: Option<usize>,
+    /// Treat `block` as a regular expression instead of a comma-separated substring list
+    #[arg(long)]
+    regex: bool,
+    /// Regular expression; block names matching it are excluded from results
+    #[arg(long)]
+    exclude: Option<String>,
+    /// Output format for results: text (default), json, or csv
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Scan the region directory for corrupt chunks instead of searching for blocks
+    #[arg(long)]
+    scan: bool,
+    /// Used with --scan: repair recoverable chunks and drop unrecoverable ones in place
+    #[arg(long)]
+    repair: bool,
+    /// Defragment the region directory, repacking live chunks to reclaim dead space
+    #[arg(long)]
+    compact: bool,
+    /// Restrict the search to one dimension (default: all discovered dimensions)
+    #[arg(long, value_enum)]
+    dimension: Option<DimensionFilter>,
 }
 
 fn main() {
@@ -118,6 +305,23 @@ fn main() {
         }
     };
 
+    if args.scan || args.compact {
+        let path = args
+            .path
+            .or(config.path)
+            .expect("No region path provided (directory of .mca files)");
+        let dimension_filter = args.dimension.or(config.dimension).unwrap_or_default();
+        for (dimension, dir) in discover_dimensions(&path, dimension_filter) {
+            println!("=== {dimension:?} ({}) ===", dir.display());
+            if args.scan {
+                scan::scan_directory(&dir, args.repair).expect("Failed to scan region directory.");
+            } else {
+                scan::compact_directory(&dir).expect("Failed to compact region directory.");
+            }
+        }
+        return;
+    }
+
     let block = args
         .block
         .or(config.block)
@@ -130,36 +334,77 @@ fn main() {
     let show_all = args.show_all;
     let max_distance = args.max_distance.or(config.max_distance);
     let chunk_distance_filter = max_distance.map(|m| (home.unwrap_or((0, 0)), m));
+    let min_y = args.min_y.or(config.min_y);
+    let max_y = args.max_y.or(config.max_y);
+    let y_bounds = (min_y.is_some() || max_y.is_some())
+        .then(|| (min_y.unwrap_or(i32::MIN), max_y.unwrap_or(i32::MAX)));
+    let min_count = args.min_count.or(config.min_count);
+    let use_regex = args.regex || config.regex.unwrap_or(false);
+    let matcher = BlockMatcher::parse(&block, use_regex).expect("Invalid block pattern.");
+    let exclude = args
+        .exclude
+        .or(config.exclude)
+        .map(|pattern| regex::Regex::new(&pattern).expect("Invalid --exclude pattern."));
+    let format = args.format.or(config.format).unwrap_or_default();
+    let dimension_filter = args.dimension.or(config.dimension).unwrap_or_default();
 
-    let paths: Vec<_> = fs::read_dir(path)
-        .expect("Invalid region path.")
-        .flatten()
-        .map(|x| x.path())
-        .collect();
-    let results: Vec<BlockResults> = paths
+    let region_files: Vec<(Dimension, std::path::PathBuf)> =
+        discover_dimensions(&path, dimension_filter)
+            .into_iter()
+            .flat_map(|(dimension, dir)| {
+                fs::read_dir(&dir)
+                    .unwrap_or_else(|_| panic!("Invalid region path: {}", dir.display()))
+                    .flatten()
+                    .map(move |entry| (dimension, entry.path()))
+            })
+            .collect();
+
+    let find_options = FindBlocksOptions {
+        exclude: exclude.as_ref(),
+        chunk_distance_filter,
+        y_bounds,
+        min_count,
+    };
+
+    let results: Vec<BlockResults> = region_files
         .par_iter()
-        .flat_map(|path| {
+        .flat_map(|(dimension, path)| {
             find_blocks(
                 path.to_str().unwrap(),
                 fs::File::open(path).unwrap(),
-                &block,
-                chunk_distance_filter,
+                *dimension,
+                &matcher,
+                &find_options,
             )
             .unwrap()
         })
         .collect();
 
-    println!("\n\n\n");
-    println!("Found chunks: {}", results.len());
     let mut sorted_results = results;
     if let Some((home_x, home_z)) = home {
-        sorted_results.sort_by_key(|r| (r.chunk.0 - home_x).pow(2) + (r.chunk.2 - home_z).pow(2));
+        sorted_results.sort_by_key(|r| {
+            let dx = (r.chunk.0 - home_x) as i64;
+            let dz = (r.chunk.2 - home_z) as i64;
+            (r.dimension, dx.pow(2) + dz.pow(2))
+        });
     }
 
-    for r in sorted_results {
+    match format {
+        OutputFormat::Text => {
+            println!("\n\n\n");
+            println!("Found chunks: {}", sorted_results.len());
+            print_text(&sorted_results, show_all);
+        }
+        OutputFormat::Json => print_json(&sorted_results, show_all).expect("Failed to write JSON."),
+        OutputFormat::Csv => print_csv(&sorted_results, home).expect("Failed to write CSV."),
+    }
+}
+
+fn print_text(results: &[BlockResults], show_all: bool) {
+    for r in results {
         if show_all {
-            for (b, n) in r.blocks {
-                println!("{} {} {} - {}", b.0, b.1, b.2, n)
+            for (b, n) in &r.blocks {
+                println!("[{:?}] {} {} {} - {}", r.dimension, b.0, b.1, b.2, n)
             }
         } else {
             let block_counts = r
@@ -170,8 +415,123 @@ fn main() {
                     acc
                 });
             for (b, count) in block_counts {
-                println!("{} {} {} - {} ({})", r.chunk.0, r.chunk.1, r.chunk.2, b, count)
+                println!(
+                    "[{:?}] {} {} {} - {} ({})",
+                    r.dimension, r.chunk.0, r.chunk.1, r.chunk.2, b, count
+                )
+            }
+        }
+    }
+}
+
+/// A single matched block, used for the `--format json` `show_all` view.
+#[derive(serde::Serialize)]
+struct BlockEntry {
+    x: i32,
+    y: i32,
+    z: i32,
+    name: String,
+}
+
+/// A matched block name aggregated by count, used for the `--format json`
+/// default (non-`show_all`) view.
+#[derive(serde::Serialize)]
+struct AggregatedBlockEntry {
+    name: String,
+    count: usize,
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum ChunkBlocks {
+    All(Vec<BlockEntry>),
+    Aggregated(Vec<AggregatedBlockEntry>),
+}
+
+#[derive(serde::Serialize)]
+struct ChunkOutput {
+    dimension: Dimension,
+    chunk: (i32, i32, i32),
+    blocks: ChunkBlocks,
+}
+
+fn print_json(results: &[BlockResults], show_all: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let output: Vec<ChunkOutput> = results
+        .iter()
+        .map(|r| {
+            let blocks = if show_all {
+                ChunkBlocks::All(
+                    r.blocks
+                        .iter()
+                        .map(|(b, name)| BlockEntry {
+                            x: b.0,
+                            y: b.1,
+                            z: b.2,
+                            name: name.clone(),
+                        })
+                        .collect(),
+                )
+            } else {
+                let mut counts = std::collections::HashMap::new();
+                for (_, name) in &r.blocks {
+                    *counts.entry(name.clone()).or_insert(0usize) += 1;
+                }
+                ChunkBlocks::Aggregated(
+                    counts
+                        .into_iter()
+                        .map(|(name, count)| AggregatedBlockEntry { name, count })
+                        .collect(),
+                )
+            };
+            ChunkOutput {
+                dimension: r.dimension,
+                chunk: r.chunk,
+                blocks,
             }
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// One flattened row of the `--format csv` output. `distance_from_home` is
+/// computed within the row's own dimension's coordinate space.
+#[derive(serde::Serialize)]
+struct CsvRecord {
+    dimension: Dimension,
+    x: i32,
+    y: i32,
+    z: i32,
+    name: String,
+    distance_from_home: f64,
+}
+
+fn print_csv(
+    results: &[BlockResults],
+    home: Option<(i32, i32)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    for r in results {
+        for (b, name) in &r.blocks {
+            let distance_from_home = match home {
+                Some((home_x, home_z)) => {
+                    let dx = (b.0 - home_x) as i64;
+                    let dz = (b.2 - home_z) as i64;
+                    ((dx.pow(2) + dz.pow(2)) as f64).sqrt()
+                }
+                None => 0.0,
+            };
+            writer.serialize(CsvRecord {
+                dimension: r.dimension,
+                x: b.0,
+                y: b.1,
+                z: b.2,
+                name: name.clone(),
+                distance_from_home,
+            })?;
         }
     }
+    writer.flush()?;
+    Ok(())
 }
@@ -0,0 +1,672 @@
+use fastanvil::Chunk;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const SECTOR_SIZE: u64 = 4096;
+const HEADER_SECTORS: u64 = 2;
+const LOCATION_TABLE_ENTRIES: usize = 1024;
+
+/// One entry of a region file's 4 KiB location table: a 3-byte big-endian
+/// sector offset followed by a 1-byte sector count.
+#[derive(Debug, Clone, Copy, Default)]
+struct ChunkLocation {
+    sector_offset: u32,
+    sector_count: u8,
+}
+
+impl ChunkLocation {
+    fn is_present(&self) -> bool {
+        self.sector_offset != 0 && self.sector_count != 0
+    }
+}
+
+/// Why a single chunk was flagged as corrupt.
+#[derive(Debug)]
+pub enum ChunkIssue {
+    /// The chunk's location-table sectors or the declared length in its 5-byte
+    /// header run past the end of the file, or the declared length is zero.
+    LengthOverrun,
+    /// Decompressing the chunk payload with its declared compression type failed.
+    DecompressionFailed,
+    /// The decoded NBT is missing a tag required of every chunk.
+    MissingTag(&'static str),
+}
+
+impl std::fmt::Display for ChunkIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkIssue::LengthOverrun => write!(f, "declared length overruns region file"),
+            ChunkIssue::DecompressionFailed => write!(f, "decompression failed"),
+            ChunkIssue::MissingTag(tag) => write!(f, "missing required tag `{tag}`"),
+        }
+    }
+}
+
+/// Per-region totals produced by [`scan_region`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RegionStats {
+    pub total: usize,
+    pub valid: usize,
+    pub corrupt: usize,
+    pub missing: usize,
+}
+
+/// A single corrupt chunk, identified by its in-region chunk coordinates.
+#[derive(Debug)]
+pub struct CorruptChunk {
+    pub chunk_x: u8,
+    pub chunk_z: u8,
+    pub issue: ChunkIssue,
+}
+
+/// Result of scanning one region file.
+pub struct ScanReport {
+    pub stats: RegionStats,
+    pub corrupt_chunks: Vec<CorruptChunk>,
+}
+
+fn read_location_table<S: Read>(stream: &mut S) -> io::Result<Vec<ChunkLocation>> {
+    let mut header = [0u8; SECTOR_SIZE as usize];
+    stream.read_exact(&mut header)?;
+
+    let mut locations = Vec::with_capacity(LOCATION_TABLE_ENTRIES);
+    for entry in header.chunks_exact(4) {
+        let sector_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]);
+        let sector_count = entry[3];
+        locations.push(ChunkLocation {
+            sector_offset,
+            sector_count,
+        });
+    }
+    Ok(locations)
+}
+
+fn skip_timestamp_table<S: Seek>(stream: &mut S) -> io::Result<()> {
+    stream.seek(SeekFrom::Current(SECTOR_SIZE as i64))?;
+    Ok(())
+}
+
+fn decompress(compression: u8, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match compression {
+        1 => flate2::read::GzDecoder::new(payload).read_to_end(&mut out)?,
+        2 => flate2::read::ZlibDecoder::new(payload).read_to_end(&mut out)?,
+        3 => {
+            out.extend_from_slice(payload);
+            out.len()
+        }
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown compression type {other}"),
+            ))
+        }
+    };
+    Ok(out)
+}
+
+fn validate_chunk_nbt(data: &[u8]) -> Result<(), ChunkIssue> {
+    // `complete::Chunk::from_bytes` already fails to parse if `xPos`/`zPos` are
+    // absent, so only `Status` and `Sections` need an explicit post-parse check.
+    let chunk = fastanvil::complete::Chunk::from_bytes(data)
+        .map_err(|_| ChunkIssue::MissingTag("xPos"))?;
+    if chunk.status.is_empty() {
+        return Err(ChunkIssue::MissingTag("Status"));
+    }
+    if chunk.y_range().is_empty() {
+        return Err(ChunkIssue::MissingTag("Sections"));
+    }
+    Ok(())
+}
+
+/// Walk a single region file, validating every present chunk the way
+/// minecraft-regions-tool does: header bounds, declared-compression
+/// decompression, and required NBT tags.
+pub fn scan_region<S: Read + Seek>(stream: &mut S, file_len: u64) -> io::Result<ScanReport> {
+    let locations = read_location_table(stream)?;
+    skip_timestamp_table(stream)?;
+
+    let mut stats = RegionStats::default();
+    let mut corrupt_chunks = Vec::new();
+
+    for (index, location) in locations.iter().enumerate() {
+        if !location.is_present() {
+            stats.missing += 1;
+            continue;
+        }
+        stats.total += 1;
+
+        let chunk_x = (index % 32) as u8;
+        let chunk_z = (index / 32) as u8;
+
+        let chunk_start = location.sector_offset as u64 * SECTOR_SIZE;
+        let chunk_sectors = location.sector_count as u64 * SECTOR_SIZE;
+        if chunk_start + 5 > file_len || chunk_start + chunk_sectors > file_len {
+            stats.corrupt += 1;
+            corrupt_chunks.push(CorruptChunk {
+                chunk_x,
+                chunk_z,
+                issue: ChunkIssue::LengthOverrun,
+            });
+            continue;
+        }
+
+        stream.seek(SeekFrom::Start(chunk_start))?;
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header)?;
+        let declared_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let compression = header[4];
+
+        // `declared_len` includes the compression-type byte itself, so anything
+        // under 1 can't hold a payload and `declared_len - 1` would underflow below.
+        if declared_len < 1 || chunk_start + 4 + declared_len > file_len {
+            stats.corrupt += 1;
+            corrupt_chunks.push(CorruptChunk {
+                chunk_x,
+                chunk_z,
+                issue: ChunkIssue::LengthOverrun,
+            });
+            continue;
+        }
+
+        let mut payload = vec![0u8; (declared_len - 1) as usize];
+        stream.read_exact(&mut payload)?;
+
+        let issue = match decompress(compression, &payload) {
+            Ok(data) => validate_chunk_nbt(&data).err(),
+            Err(_) => Some(ChunkIssue::DecompressionFailed),
+        };
+
+        match issue {
+            Some(issue) => {
+                stats.corrupt += 1;
+                corrupt_chunks.push(CorruptChunk {
+                    chunk_x,
+                    chunk_z,
+                    issue,
+                });
+            }
+            None => stats.valid += 1,
+        }
+    }
+
+    Ok(ScanReport {
+        stats,
+        corrupt_chunks,
+    })
+}
+
+/// Try every other compression method against `payload`, returning the method
+/// byte of the first one that decodes cleanly and validates.
+fn find_working_compression(payload: &[u8], declared: u8) -> Option<u8> {
+    [1u8, 2, 3]
+        .into_iter()
+        .filter(|&method| method != declared)
+        .find(|&method| {
+            decompress(method, payload)
+                .map(|data| validate_chunk_nbt(&data).is_ok())
+                .unwrap_or(false)
+        })
+}
+
+/// Repair a region file in place: zero the location-table entry for any
+/// unrecoverable chunk, or rewrite the 1-byte compression marker when the
+/// declared method was wrong but another method decompresses cleanly.
+pub fn repair_region<S: Read + Write + Seek>(stream: &mut S, file_len: u64) -> io::Result<RegionStats> {
+    let locations = read_location_table(stream)?;
+    skip_timestamp_table(stream)?;
+
+    let mut stats = RegionStats::default();
+
+    for (index, location) in locations.iter().enumerate() {
+        if !location.is_present() {
+            stats.missing += 1;
+            continue;
+        }
+        stats.total += 1;
+
+        let chunk_start = location.sector_offset as u64 * SECTOR_SIZE;
+        let chunk_sectors = location.sector_count as u64 * SECTOR_SIZE;
+        if chunk_start + 5 > file_len || chunk_start + chunk_sectors > file_len {
+            zero_location_entry(stream, index)?;
+            stats.corrupt += 1;
+            continue;
+        }
+
+        stream.seek(SeekFrom::Start(chunk_start))?;
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header)?;
+        let declared_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let compression = header[4];
+
+        if declared_len < 1 || chunk_start + 4 + declared_len > file_len {
+            zero_location_entry(stream, index)?;
+            stats.corrupt += 1;
+            continue;
+        }
+
+        let mut payload = vec![0u8; (declared_len - 1) as usize];
+        stream.read_exact(&mut payload)?;
+
+        let decodes = decompress(compression, &payload)
+            .map(|data| validate_chunk_nbt(&data).is_ok())
+            .unwrap_or(false);
+
+        if decodes {
+            stats.valid += 1;
+            continue;
+        }
+
+        match find_working_compression(&payload, compression) {
+            Some(working) => {
+                stream.seek(SeekFrom::Start(chunk_start + 4))?;
+                stream.write_all(&[working])?;
+                stats.valid += 1;
+            }
+            None => {
+                zero_location_entry(stream, index)?;
+                stats.corrupt += 1;
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+fn zero_location_entry<S: Write + Seek>(stream: &mut S, index: usize) -> io::Result<()> {
+    stream.seek(SeekFrom::Start(index as u64 * 4))?;
+    stream.write_all(&[0u8; 4])
+}
+
+/// Scan every `.mca` file in `dir`, printing per-file and aggregate
+/// corruption counts. With `repair`, also patches recoverable chunks and
+/// zeroes unrecoverable ones in place.
+pub fn scan_directory(dir: &std::path::Path, repair: bool) -> io::Result<()> {
+    let mut totals = RegionStats::default();
+    let mut files: Vec<_> = std::fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+    files.sort();
+
+    for path in files {
+        if path.extension().and_then(|e| e.to_str()) != Some("mca") {
+            continue;
+        }
+        let filename = path.display().to_string();
+        let file_len = std::fs::metadata(&path)?.len();
+        if file_len < HEADER_SECTORS * SECTOR_SIZE {
+            println!("{filename}: too small to contain a region header, skipping");
+            continue;
+        }
+
+        let stats = if repair {
+            let mut file = std::fs::OpenOptions::new().read(true).write(true).open(&path)?;
+            repair_region(&mut file, file_len)?
+        } else {
+            let mut file = std::fs::File::open(&path)?;
+            let report = scan_region(&mut file, file_len)?;
+            for corrupt in &report.corrupt_chunks {
+                println!(
+                    "{filename}: chunk ({}, {}) corrupt: {}",
+                    corrupt.chunk_x, corrupt.chunk_z, corrupt.issue
+                );
+            }
+            report.stats
+        };
+
+        println!(
+            "{filename}: {} total, {} valid, {} corrupt, {} missing",
+            stats.total, stats.valid, stats.corrupt, stats.missing
+        );
+
+        totals.total += stats.total;
+        totals.valid += stats.valid;
+        totals.corrupt += stats.corrupt;
+        totals.missing += stats.missing;
+    }
+
+    println!(
+        "\nOverall: {} total, {} valid, {} corrupt, {} missing",
+        totals.total, totals.valid, totals.corrupt, totals.missing
+    );
+
+    Ok(())
+}
+
+/// A live chunk pulled out of the region during compaction, along with the
+/// bytes needed to reinstate it at its new, denser location.
+struct LiveChunk {
+    index: usize,
+    offset_sectors: u32,
+    sector_count: u8,
+    timestamp: u32,
+    data: Vec<u8>,
+}
+
+/// Repack every live chunk in `stream` contiguously starting at sector 2,
+/// updating the location table as each chunk moves and preserving the
+/// timestamp table untouched. Returns the new file length: the end of the
+/// last packed chunk.
+///
+/// Every live chunk's bytes are read into memory up front, before any data is
+/// written, so a chunk's move never has to race a read of stale data. Two
+/// entries that claim overlapping sectors are themselves a form of
+/// corruption: the later-timestamped one is treated as authoritative and
+/// packed first, while the other keeps whatever bytes its own claimed range
+/// held and is packed afterwards rather than being dropped. Because both
+/// chunks keep their full claimed ranges, the returned length can exceed
+/// the input length when entries overlap. Each chunk's location-table entry
+/// is written and flushed right after its data, so an interruption between
+/// two moves leaves every already-moved chunk's entry pointing at its new
+/// data; a region interrupted partway through compaction can still leave
+/// the chunk being moved, or not-yet-processed chunks, unreadable if their
+/// original sectors were already overwritten by a move. Entries whose sectors run past the
+/// file (left for `scan`/`repair` to diagnose) have their location-table
+/// entry zeroed here instead, so the shrunk file doesn't keep a dangling
+/// pointer into space that's about to be truncated away.
+pub fn compact_region<S: Read + Write + Seek>(stream: &mut S, file_len: u64) -> io::Result<u64> {
+    let locations = read_location_table(stream)?;
+    let mut timestamp_table = [0u8; SECTOR_SIZE as usize];
+    stream.read_exact(&mut timestamp_table)?;
+    let timestamps: Vec<u32> = timestamp_table
+        .chunks_exact(4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    let mut live = Vec::new();
+    for (index, location) in locations.iter().enumerate() {
+        if !location.is_present() {
+            continue;
+        }
+        let start = location.sector_offset as u64 * SECTOR_SIZE;
+        let byte_len = location.sector_count as u64 * SECTOR_SIZE;
+        if start + byte_len > file_len {
+            // Left for `scan`/`repair` to diagnose; zero the entry so the
+            // shrunk file doesn't keep a dangling pointer into truncated space.
+            zero_location_entry(stream, index)?;
+            continue;
+        }
+
+        let mut data = vec![0u8; byte_len as usize];
+        stream.seek(SeekFrom::Start(start))?;
+        stream.read_exact(&mut data)?;
+        live.push(LiveChunk {
+            index,
+            offset_sectors: location.sector_offset,
+            sector_count: location.sector_count,
+            timestamp: timestamps[index],
+            data,
+        });
+    }
+
+    // Two entries claiming overlapping sectors is itself a form of corruption;
+    // the later-timestamped (authoritative) chunk is packed first and the
+    // other is demoted to pack afterwards, rather than dropped.
+    let mut demoted = vec![false; live.len()];
+    for i in 0..live.len() {
+        for j in (i + 1)..live.len() {
+            let a = live[i].offset_sectors..live[i].offset_sectors + live[i].sector_count as u32;
+            let b = live[j].offset_sectors..live[j].offset_sectors + live[j].sector_count as u32;
+            let overlaps = a.start < b.end && b.start < a.end;
+            if !overlaps {
+                continue;
+            }
+            if live[i].timestamp >= live[j].timestamp {
+                demoted[j] = true;
+            } else {
+                demoted[i] = true;
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..live.len()).collect();
+    order.sort_by_key(|&i| (demoted[i], live[i].offset_sectors));
+
+    let mut next_sector = HEADER_SECTORS as u32;
+    for i in order {
+        let chunk = &live[i];
+        let target_start = next_sector as u64 * SECTOR_SIZE;
+
+        stream.seek(SeekFrom::Start(target_start))?;
+        stream.write_all(&chunk.data)?;
+
+        let new_offset = next_sector.to_be_bytes();
+        stream.seek(SeekFrom::Start(chunk.index as u64 * 4))?;
+        stream.write_all(&[new_offset[1], new_offset[2], new_offset[3], chunk.sector_count])?;
+        stream.flush()?;
+
+        next_sector += chunk.sector_count as u32;
+    }
+
+    Ok(next_sector as u64 * SECTOR_SIZE)
+}
+
+/// Compact every `.mca` file in `dir` in place, truncating each file to the
+/// end of its last packed chunk to drop the dead space the scan subsystem's
+/// header parsing already knows how to walk, and reporting bytes reclaimed
+/// per file (negative when overlapping entries make a file grow).
+pub fn compact_directory(dir: &std::path::Path) -> io::Result<()> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+    files.sort();
+
+    let mut total_saved = 0i64;
+    for path in files {
+        if path.extension().and_then(|e| e.to_str()) != Some("mca") {
+            continue;
+        }
+        let filename = path.display().to_string();
+        let file_len = std::fs::metadata(&path)?.len();
+        if file_len < HEADER_SECTORS * SECTOR_SIZE {
+            println!("{filename}: too small to contain a region header, skipping");
+            continue;
+        }
+
+        let mut file = std::fs::OpenOptions::new().read(true).write(true).open(&path)?;
+        let new_len = compact_region(&mut file, file_len)?;
+        file.set_len(new_len)?;
+
+        let saved = file_len as i64 - new_len as i64;
+        total_saved += saved;
+        println!("{filename}: {file_len} -> {new_len} bytes ({saved} saved)");
+    }
+
+    println!("\nTotal reclaimed: {total_saved} bytes");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn empty_header() -> Vec<u8> {
+        vec![0u8; (HEADER_SECTORS * SECTOR_SIZE) as usize]
+    }
+
+    fn set_location(buf: &mut [u8], index: usize, sector_offset: u32, sector_count: u8) {
+        let offset_bytes = sector_offset.to_be_bytes();
+        let entry = &mut buf[index * 4..index * 4 + 4];
+        entry.copy_from_slice(&[offset_bytes[1], offset_bytes[2], offset_bytes[3], sector_count]);
+    }
+
+    fn set_timestamp(buf: &mut [u8], index: usize, timestamp: u32) {
+        let entry = &mut buf[SECTOR_SIZE as usize + index * 4..SECTOR_SIZE as usize + index * 4 + 4];
+        entry.copy_from_slice(&timestamp.to_be_bytes());
+    }
+
+    fn pad_to_sector(buf: &mut Vec<u8>) {
+        while buf.len() % SECTOR_SIZE as usize != 0 {
+            buf.push(0);
+        }
+    }
+
+    /// Appends a chunk with the given declared length (header value, includes
+    /// the compression-type byte) and raw payload bytes, sector-padded, and
+    /// returns how many sectors it occupies.
+    fn push_chunk(buf: &mut Vec<u8>, declared_len: u32, compression: u8, payload: &[u8]) -> u8 {
+        let start = buf.len();
+        buf.extend_from_slice(&declared_len.to_be_bytes());
+        buf.push(compression);
+        buf.extend_from_slice(payload);
+        pad_to_sector(buf);
+        ((buf.len() - start) / SECTOR_SIZE as usize) as u8
+    }
+
+    #[test]
+    fn scan_flags_zero_declared_length_instead_of_crashing() {
+        let mut buf = empty_header();
+        let sectors = push_chunk(&mut buf, 0, 3, &[]);
+        set_location(&mut buf, 0, HEADER_SECTORS as u32, sectors);
+
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let report = scan_region(&mut cursor, file_len).expect("scan should not panic");
+
+        assert_eq!(report.stats.corrupt, 1);
+        assert_eq!(report.stats.valid, 0);
+        assert!(matches!(
+            report.corrupt_chunks[0].issue,
+            ChunkIssue::LengthOverrun
+        ));
+    }
+
+    #[test]
+    fn repair_zeroes_location_entry_for_zero_declared_length() {
+        let mut buf = empty_header();
+        let sectors = push_chunk(&mut buf, 0, 3, &[]);
+        set_location(&mut buf, 0, HEADER_SECTORS as u32, sectors);
+
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let stats = repair_region(&mut cursor, file_len).expect("repair should not panic");
+
+        assert_eq!(stats.corrupt, 1);
+        let locations = {
+            cursor.set_position(0);
+            read_location_table(&mut cursor).unwrap()
+        };
+        assert!(!locations[0].is_present());
+    }
+
+    #[test]
+    fn scan_flags_undecodable_chunk_as_corrupt() {
+        let mut buf = empty_header();
+        let garbage = [1u8, 2, 3, 4, 5];
+        let sectors = push_chunk(&mut buf, garbage.len() as u32 + 1, 2, &garbage);
+        set_location(&mut buf, 0, HEADER_SECTORS as u32, sectors);
+
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let report = scan_region(&mut cursor, file_len).unwrap();
+
+        assert_eq!(report.stats.corrupt, 1);
+        assert!(matches!(
+            report.corrupt_chunks[0].issue,
+            ChunkIssue::DecompressionFailed
+        ));
+    }
+
+    #[test]
+    fn scan_empty_region_has_no_chunks() {
+        let buf = empty_header();
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let report = scan_region(&mut cursor, file_len).unwrap();
+
+        assert_eq!(report.stats.total, 0);
+        assert_eq!(report.stats.missing, LOCATION_TABLE_ENTRIES);
+        assert!(report.corrupt_chunks.is_empty());
+    }
+
+    #[test]
+    fn compact_preserves_live_chunk_bytes_and_timestamp_table() {
+        let mut buf = empty_header();
+        let payload_a = b"alpha-chunk-data".to_vec();
+        let sectors_a = push_chunk(&mut buf, payload_a.len() as u32 + 1, 3, &payload_a);
+        set_location(&mut buf, 0, HEADER_SECTORS as u32, sectors_a);
+        set_timestamp(&mut buf, 0, 111);
+
+        // Leave a gap sector of dead space before the next chunk, which
+        // compaction should reclaim.
+        pad_to_sector(&mut buf);
+        buf.extend(std::iter::repeat(0u8).take(SECTOR_SIZE as usize));
+
+        let second_offset = (buf.len() / SECTOR_SIZE as usize) as u32;
+        let payload_b = b"beta-chunk-data".to_vec();
+        let sectors_b = push_chunk(&mut buf, payload_b.len() as u32 + 1, 3, &payload_b);
+        set_location(&mut buf, 1, second_offset, sectors_b);
+        set_timestamp(&mut buf, 1, 222);
+
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let new_len = compact_region(&mut cursor, file_len).unwrap();
+        assert!(new_len < file_len);
+
+        cursor.set_position(0);
+        let locations = read_location_table(&mut cursor).unwrap();
+        let mut timestamp_table = [0u8; SECTOR_SIZE as usize];
+        cursor.read_exact(&mut timestamp_table).unwrap();
+        assert_eq!(
+            u32::from_be_bytes(timestamp_table[0..4].try_into().unwrap()),
+            111
+        );
+        assert_eq!(
+            u32::from_be_bytes(timestamp_table[4..8].try_into().unwrap()),
+            222
+        );
+
+        for (index, payload) in [(0usize, &payload_a), (1usize, &payload_b)] {
+            let location = locations[index];
+            assert!(location.is_present());
+            let mut data = vec![0u8; payload.len()];
+            cursor
+                .seek(SeekFrom::Start(location.sector_offset as u64 * SECTOR_SIZE + 5))
+                .unwrap();
+            cursor.read_exact(&mut data).unwrap();
+            assert_eq!(&data, payload);
+        }
+    }
+
+    #[test]
+    fn compact_keeps_both_chunks_on_sector_overlap() {
+        let mut buf = empty_header();
+        // Chunk A's real bytes occupy sector 2, chunk B's real bytes occupy
+        // sector 3 -- but A's location entry (mis-)claims both sectors, so
+        // A's and B's claimed ranges genuinely overlap at sector 3.
+        let payload_a = b"older-overlap".to_vec();
+        push_chunk(&mut buf, payload_a.len() as u32 + 1, 3, &payload_a);
+        let payload_b = b"newer-overlap".to_vec();
+        push_chunk(&mut buf, payload_b.len() as u32 + 1, 3, &payload_b);
+
+        set_location(&mut buf, 0, HEADER_SECTORS as u32, 2);
+        set_timestamp(&mut buf, 0, 100);
+        set_location(&mut buf, 1, HEADER_SECTORS as u32 + 1, 1);
+        set_timestamp(&mut buf, 1, 200);
+
+        let file_len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        compact_region(&mut cursor, file_len).unwrap();
+
+        cursor.set_position(0);
+        let locations = read_location_table(&mut cursor).unwrap();
+        assert!(locations[0].is_present());
+        assert!(locations[1].is_present());
+
+        // The newer (authoritative) chunk keeps its original bytes; the
+        // demoted older chunk is packed afterwards rather than dropped.
+        let mut data_b = vec![0u8; payload_b.len()];
+        cursor
+            .seek(SeekFrom::Start(
+                locations[1].sector_offset as u64 * SECTOR_SIZE + 5,
+            ))
+            .unwrap();
+        cursor.read_exact(&mut data_b).unwrap();
+        assert_eq!(data_b, payload_b);
+
+        let mut data_a = vec![0u8; payload_a.len()];
+        cursor
+            .seek(SeekFrom::Start(
+                locations[0].sector_offset as u64 * SECTOR_SIZE + 5,
+            ))
+            .unwrap();
+        cursor.read_exact(&mut data_a).unwrap();
+        assert_eq!(data_a, payload_a);
+    }
+}